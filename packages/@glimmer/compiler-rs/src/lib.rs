@@ -0,0 +1,6 @@
+pub mod node_id;
+pub mod nodes;
+pub mod print;
+pub mod serialize;
+pub mod template_visitor;
+pub mod visit;
@@ -0,0 +1,361 @@
+//! Stamps every statement and expression in the AST with a stable
+//! `NodeId`, and indexes those ids so a later pass can resolve one back to
+//! its node (and its parent) without carrying a borrow of the tree around.
+//!
+//! `IdAssigner` is a `Folder`: it reuses `visit`'s traversal instead of
+//! duplicating it, overriding only `fold_spanned_statement`/
+//! `fold_spanned_expression` to stamp an id before recursing into children.
+//! `AstMap`, on the other hand, can't be written as a `Visitor` -- it needs
+//! every reference it stores to share its own `'a` lifetime, but `Visitor`'s
+//! methods take a per-call, late-bound lifetime that isn't guaranteed to be
+//! `'a`. Its indexing methods stay hand-rolled, mirroring `visit::walk_*`
+//! node for node.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::nodes::{
+    AttrNode, AttrValue, BlockStatement, ConcatParts, ElementModifierStatement, ElementNode,
+    Expression, Hash, MustacheStatement, Node, Nodes, PartialStatement, Program, Spanned,
+    Statement,
+};
+use crate::visit::Folder;
+
+/// A stable identifier for a node in the AST, assigned by `assign_ids`.
+///
+/// Cross-referencing passes -- e.g. associating a `SymbolTable` slot with
+/// the exact `PathExpression` node that allocated it -- can hold onto a
+/// `NodeId` instead of a borrow, then resolve it back through an `AstMap`
+/// once they actually need the node.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct NodeId(u32);
+
+/// Walks `node` in deterministic pre-order, stamping a monotonically
+/// increasing `NodeId` onto the root and onto every statement and
+/// expression beneath it. Only a `Program` root is descended into: for any
+/// other kind of root, the root itself is the only node that gets an id.
+pub fn assign_ids(node: Node) -> Node {
+    let mut assigner = IdAssigner { next: 0 };
+    let id = assigner.next_id();
+
+    let inner = match node.node {
+        Nodes::Program(program) => Nodes::Program(assigner.fold_program(program)),
+        other => other,
+    };
+
+    Node {
+        loc: node.loc,
+        node: inner,
+        id: Some(id),
+    }
+}
+
+struct IdAssigner {
+    next: u32,
+}
+
+impl IdAssigner {
+    fn next_id(&mut self) -> NodeId {
+        let id = NodeId(self.next);
+        self.next += 1;
+        id
+    }
+}
+
+impl Folder for IdAssigner {
+    fn fold_spanned_statement(&mut self, mut statement: Spanned<Statement>) -> Spanned<Statement> {
+        statement.id = Some(self.next_id());
+        statement.map(|s| self.fold_statement(s))
+    }
+
+    fn fold_spanned_expression(
+        &mut self,
+        mut expression: Spanned<Expression>,
+    ) -> Spanned<Expression> {
+        expression.id = Some(self.next_id());
+        expression.map(|e| self.fold_expression(e))
+    }
+}
+
+/// Resolves `NodeId`s produced by `assign_ids` back to the node -- and its
+/// parent -- they were stamped onto.
+pub struct AstMap<'a> {
+    statements: HashMap<NodeId, &'a Spanned<Statement>>,
+    expressions: HashMap<NodeId, &'a Spanned<Expression>>,
+    parents: HashMap<NodeId, NodeId>,
+}
+
+impl<'a> AstMap<'a> {
+    /// Builds an index over `node`, which must already have had
+    /// `assign_ids` run over it. Only a `Program` root is indexed; for any
+    /// other kind of root the map is empty.
+    pub fn build(node: &'a Node) -> AstMap<'a> {
+        let mut map = AstMap {
+            statements: HashMap::new(),
+            expressions: HashMap::new(),
+            parents: HashMap::new(),
+        };
+
+        if let Nodes::Program(program) = &node.node {
+            map.index_program(program, node.id);
+        }
+
+        map
+    }
+
+    pub fn statement(&self, id: NodeId) -> Option<&'a Spanned<Statement>> {
+        self.statements.get(&id).copied()
+    }
+
+    pub fn expression(&self, id: NodeId) -> Option<&'a Spanned<Expression>> {
+        self.expressions.get(&id).copied()
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.parents.get(&id).copied()
+    }
+
+    fn index_program(&mut self, program: &'a Program, parent: Option<NodeId>) {
+        for statement in &program.body {
+            self.index_statement(statement, parent);
+        }
+    }
+
+    fn index_statement(&mut self, statement: &'a Spanned<Statement>, parent: Option<NodeId>) {
+        if let (Some(id), Some(parent)) = (statement.id, parent) {
+            self.parents.insert(id, parent);
+        }
+        if let Some(id) = statement.id {
+            self.statements.insert(id, statement);
+        }
+
+        match &statement.node {
+            Statement::MustacheStatement(mustache) => self.index_mustache(mustache, statement.id),
+            Statement::BlockStatement(block) => self.index_block(block, statement.id),
+            Statement::PartialStatement(partial) => self.index_partial(partial, statement.id),
+            Statement::ElementNode(element) => self.index_element(element, statement.id),
+            Statement::MustacheComment(_) | Statement::TextNode(_) => {}
+        }
+    }
+
+    fn index_element(&mut self, element: &'a ElementNode, parent: Option<NodeId>) {
+        for attr in &element.attributes {
+            self.index_attr(attr, parent);
+        }
+        for modifier in &element.modifiers {
+            self.index_modifier(modifier, parent);
+        }
+        for child in &element.children {
+            self.index_statement(child, parent);
+        }
+    }
+
+    fn index_attr(&mut self, attr: &'a AttrNode, parent: Option<NodeId>) {
+        match &attr.value {
+            AttrValue::TextNode(_) => {}
+            AttrValue::MustacheStatement(mustache) => self.index_mustache(mustache, parent),
+            AttrValue::ConcatStatement(concat) => {
+                for part in &concat.parts {
+                    if let ConcatParts::MustacheStatement(mustache) = part {
+                        self.index_mustache(mustache, parent);
+                    }
+                }
+            }
+        }
+    }
+
+    fn index_modifier(&mut self, modifier: &'a ElementModifierStatement, parent: Option<NodeId>) {
+        for param in &modifier.params {
+            self.index_expression(param, parent);
+        }
+        self.index_hash(&modifier.hash, parent);
+    }
+
+    fn index_partial(&mut self, partial: &'a PartialStatement, parent: Option<NodeId>) {
+        for param in &partial.params {
+            self.index_expression(param, parent);
+        }
+        self.index_hash(&partial.hash, parent);
+    }
+
+    fn index_mustache(&mut self, mustache: &'a MustacheStatement, parent: Option<NodeId>) {
+        for param in &mustache.params {
+            self.index_expression(param, parent);
+        }
+        self.index_hash(&mustache.hash, parent);
+    }
+
+    fn index_block(&mut self, block: &'a BlockStatement, parent: Option<NodeId>) {
+        for param in &block.params {
+            self.index_expression(param, parent);
+        }
+        self.index_hash(&block.hash, parent);
+        self.index_program(&block.program, parent);
+        if let Some(inverse) = &block.inverse {
+            self.index_program(inverse, parent);
+        }
+    }
+
+    fn index_expression(&mut self, expression: &'a Spanned<Expression>, parent: Option<NodeId>) {
+        if let (Some(id), Some(parent)) = (expression.id, parent) {
+            self.parents.insert(id, parent);
+        }
+        if let Some(id) = expression.id {
+            self.expressions.insert(id, expression);
+        }
+
+        if let Expression::SubExpression(sub) = &expression.node {
+            for param in &sub.params {
+                self.index_expression(param, expression.id);
+            }
+            self.index_hash(&sub.hash, expression.id);
+        }
+    }
+
+    fn index_hash(&mut self, hash: &'a Hash, parent: Option<NodeId>) {
+        for pair in &hash.pairs {
+            self.index_expression(&pair.value, parent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::{
+        dummy_spanned, AttrValue, ElementModifierStatement, ElementNode, HashPair, MustachePath,
+        PathExpression, Position, SourceLocation,
+    };
+
+    fn path(original: &str) -> PathExpression {
+        PathExpression {
+            call: None,
+            data: false,
+            original: original.to_string(),
+            this: false,
+            parts: original.split('.').map(str::to_string).collect(),
+        }
+    }
+
+    fn hash_pair(key: &str, value_path: &str) -> HashPair {
+        HashPair {
+            key: key.to_string(),
+            value: dummy_spanned(Expression::PathExpression(path(value_path))),
+        }
+    }
+
+    // <div {{mod foo=bar}} class={{cls val}}>{{baz qux=zap}}</div>
+    fn tree() -> Node {
+        let attr = AttrNode {
+            name: "class".to_string(),
+            value: AttrValue::MustacheStatement(MustacheStatement {
+                path: MustachePath::Path(path("cls")),
+                params: vec![dummy_spanned(Expression::PathExpression(path("val")))],
+                hash: Hash { pairs: Vec::new() },
+                escaped: true,
+            }),
+        };
+
+        let modifier = ElementModifierStatement {
+            path: path("mod"),
+            params: Vec::new(),
+            hash: Hash {
+                pairs: vec![hash_pair("foo", "bar")],
+            },
+        };
+
+        let child = Statement::MustacheStatement(MustacheStatement {
+            path: MustachePath::Path(path("baz")),
+            params: Vec::new(),
+            hash: Hash {
+                pairs: vec![hash_pair("qux", "zap")],
+            },
+            escaped: true,
+        });
+
+        let element = ElementNode {
+            tag: "div".to_string(),
+            self_closing: false,
+            attributes: vec![attr],
+            block_params: Vec::new(),
+            modifiers: vec![modifier],
+            comments: Vec::new(),
+            children: vec![dummy_spanned(child)],
+        };
+
+        Node {
+            loc: SourceLocation {
+                source: None,
+                start: Position::default(),
+                end: Position::default(),
+            },
+            node: Nodes::Program(Program {
+                body: vec![dummy_spanned(Statement::ElementNode(element))],
+                block_params: Vec::new(),
+            }),
+            id: None,
+        }
+    }
+
+    #[test]
+    fn assign_ids_stamps_every_node_in_deterministic_pre_order() {
+        let node = assign_ids(tree());
+
+        assert_eq!(node.id, Some(NodeId(0)));
+
+        let program = match &node.node {
+            Nodes::Program(program) => program,
+            _ => panic!("expected a Program"),
+        };
+        let div = &program.body[0];
+        assert_eq!(div.id, Some(NodeId(1)));
+
+        let element = match &div.node {
+            Statement::ElementNode(element) => element,
+            _ => panic!("expected an ElementNode"),
+        };
+
+        // Attribute: `class={{cls val}}` -- the `val` param is the first
+        // expression reached after the enclosing statement.
+        let attr_mustache = match &element.attributes[0].value {
+            AttrValue::MustacheStatement(mustache) => mustache,
+            _ => panic!("expected a MustacheStatement attr value"),
+        };
+        assert_eq!(attr_mustache.params[0].id, Some(NodeId(2)));
+
+        // Modifier: `{{mod foo=bar}}` -- its hash value is reached next.
+        assert_eq!(element.modifiers[0].hash.pairs[0].value.id, Some(NodeId(3)));
+
+        // Child statement `{{baz qux=zap}}` and its own hash value.
+        let child = &element.children[0];
+        assert_eq!(child.id, Some(NodeId(4)));
+        match &child.node {
+            Statement::MustacheStatement(mustache) => {
+                assert_eq!(mustache.hash.pairs[0].value.id, Some(NodeId(5)));
+            }
+            _ => panic!("expected a MustacheStatement"),
+        }
+    }
+
+    #[test]
+    fn ast_map_resolves_parents_through_attrs_modifiers_and_hash_pairs() {
+        let node = assign_ids(tree());
+        let map = AstMap::build(&node);
+
+        // Attr and modifier expressions share the enclosing element
+        // statement as their parent; the child statement's hash value's
+        // parent is the child statement itself, not the element.
+        assert_eq!(map.parent(NodeId(1)), Some(NodeId(0)));
+        assert_eq!(map.parent(NodeId(2)), Some(NodeId(1)));
+        assert_eq!(map.parent(NodeId(3)), Some(NodeId(1)));
+        assert_eq!(map.parent(NodeId(4)), Some(NodeId(1)));
+        assert_eq!(map.parent(NodeId(5)), Some(NodeId(4)));
+
+        assert!(map.statement(NodeId(1)).is_some());
+        assert!(map.expression(NodeId(2)).is_some());
+        assert!(map.expression(NodeId(3)).is_some());
+        assert!(map.statement(NodeId(4)).is_some());
+        assert!(map.expression(NodeId(5)).is_some());
+    }
+}
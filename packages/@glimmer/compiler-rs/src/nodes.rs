@@ -1,14 +1,18 @@
 use std::any::Any;
 use std::default::Default;
 
-#[derive(Clone, Debug)]
+use serde::{Deserialize, Serialize};
+
+use crate::node_id::NodeId;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SourceLocation {
     pub source: Option<String>,
     pub start: Position,
     pub end: Position,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Position {
     /// >= 1
     pub line: u16,
@@ -32,97 +36,143 @@ impl Default for Position {
     }
 }
 
-#[derive(Clone, Debug)]
+/// A node paired with the source range it came from.
+///
+/// Only the top-level `Node` used to carry a `loc`, which meant nothing
+/// below it -- a single statement or sub-expression, say -- could be
+/// pointed at directly in a diagnostic. Wrapping a node in `Spanned<T>`
+/// instead of growing every AST type its own `loc` field keeps the node
+/// types themselves focused on syntax.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: SourceLocation,
+    /// Set by `node_id::assign_ids`; `None` until that pass has run.
+    pub id: Option<NodeId>,
+}
+
+impl<T> Spanned<T> {
+    /// Applies `f` to the wrapped node, keeping the original span and id.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned {
+            node: f(self.node),
+            span: self.span,
+            id: self.id,
+        }
+    }
+}
+
+/// Wraps `node` with an explicit `span`.
+pub fn respan<T>(span: SourceLocation, node: T) -> Spanned<T> {
+    Spanned {
+        node,
+        span,
+        id: None,
+    }
+}
+
+/// Wraps `node` with a placeholder span, for synthesized nodes that have no
+/// real source location.
+pub fn dummy_spanned<T>(node: T) -> Spanned<T> {
+    Spanned {
+        node,
+        span: SourceLocation {
+            source: None,
+            start: Position::default(),
+            end: Position::default(),
+        },
+        id: None,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Program {
-    pub body: Vec<Statement>,
+    pub body: Vec<Spanned<Statement>>,
     pub block_params: Vec<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Statement {
     MustacheStatement(MustacheStatement),
     BlockStatement(BlockStatement),
     PartialStatement(PartialStatement),
     MustacheComment(MustacheCommentStatement),
-    TextNode,
-    ElementNode,
+    TextNode(TextNode),
+    ElementNode(ElementNode),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CallExpression {
     PathExpression(PathExpression),
     SubExpression(SubExpression),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Call {
     pub name: Option<CallExpression>,
     pub path: PathExpression,
-    pub params: Vec<Expression>,
+    pub params: Vec<Spanned<Expression>>,
     pub hash: Hash,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum MustachePath {
     Path(PathExpression),
     Literal(Literal),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MustacheStatement {
     pub path: MustachePath,
-    pub params: Vec<Expression>,
+    pub params: Vec<Spanned<Expression>>,
     pub hash: Hash,
     pub escaped: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct BlockStatement {
     pub path: PathExpression,
-    pub params: Vec<Expression>,
+    pub params: Vec<Spanned<Expression>>,
     pub hash: Hash,
     pub program: Program,
     pub inverse: Option<Program>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ElementModifierStatement {
     pub path: PathExpression,
-    pub params: Vec<Expression>,
+    pub params: Vec<Spanned<Expression>>,
     pub hash: Hash,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PartialStatement {
     pub name: CallExpression,
-    pub params: Vec<Expression>,
+    pub params: Vec<Spanned<Expression>>,
     pub hash: Hash,
     pub ident: String,
     pub strip: StripFlags,
 }
 
-pub fn is_call(node: &Any) -> bool {
+pub fn is_call(node: &dyn Any) -> bool {
     node.downcast_ref::<SubExpression>().is_some()
         || node
             .downcast_ref::<MustacheStatement>()
-            .map(|mustache| match mustache.path {
-                MustachePath::Path(_) => true,
-                _ => false,
-            })
+            .map(|mustache| matches!(mustache.path, MustachePath::Path(_)))
             .unwrap_or(false)
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CommentStatement {
     pub value: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MustacheCommentStatement {
     pub value: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ElementNode {
     pub tag: String,
     pub self_closing: bool,
@@ -130,63 +180,69 @@ pub struct ElementNode {
     pub block_params: Vec<String>,
     pub modifiers: Vec<ElementModifierStatement>,
     pub comments: Vec<MustacheCommentStatement>,
-    pub children: Vec<Statement>,
+    pub children: Vec<Spanned<Statement>>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum AttrValue {
     TextNode(TextNode),
     MustacheStatement(MustacheStatement),
     ConcatStatement(ConcatStatement),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AttrNode {
     pub name: String,
     pub value: AttrValue,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct TextNode {
     pub chars: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ConcatParts {
     TextNode(TextNode),
     MustacheStatement(MustacheStatement),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ConcatStatement {
     pub parts: Vec<ConcatParts>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Expression {
     SubExpression(SubExpression),
     PathExpression(PathExpression),
     Literal(Literal),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SubExpression {
-    pub call: Box<Call>,
+    /// `None` for a path/sub-expression that wasn't itself produced by
+    /// parsing a call -- without the `Option`, every `Call` would need its
+    /// own `path`, which would need its own `call`, with no base case.
+    pub call: Option<Box<Call>>,
     pub path: PathExpression,
-    pub params: Vec<Expression>,
+    pub params: Vec<Spanned<Expression>>,
     pub hash: Hash,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PathExpression {
-    pub call: Box<Call>,
+    /// `None` for a path/sub-expression that wasn't itself produced by
+    /// parsing a call -- without the `Option`, every `Call` would need its
+    /// own `path`, which would need its own `call`, with no base case.
+    pub call: Option<Box<Call>>,
     pub data: bool,
     pub original: String,
     pub this: bool,
     pub parts: Vec<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Literal {
     StringLiteral(StringLiteral),
     BooleanLiteral(BooleanLiteral),
@@ -195,13 +251,13 @@ pub enum Literal {
     NullLiteral(NullLiteral),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct StringLiteral {
     pub value: String,
     pub original: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct BooleanLiteral {
     pub value: bool,
     pub original: bool,
@@ -209,56 +265,78 @@ pub struct BooleanLiteral {
 
 /// The type is `f64` because JavaScript `number` type is an IEEE 754 64-bit
 /// floating-point number.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct NumberLiteral {
     pub value: f64,
     pub original: f64,
 }
 
+// `f64`'s own `Serialize`/`Deserialize` round-trips fine for the binary
+// backend, but JSON has no representation for NaN or infinity: serde_json
+// writes them as `null`, which then fails to decode as an `f64`. Serializing the raw bits instead
+// of the float gives both backends an exact, deterministic round trip.
+impl Serialize for NumberLiteral {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.value.to_bits(), self.original.to_bits()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NumberLiteral {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (value, original) = <(u64, u64)>::deserialize(deserializer)?;
+        Ok(NumberLiteral {
+            value: f64::from_bits(value),
+            original: f64::from_bits(original),
+        })
+    }
+}
+
 /// A placeholder type to represent the JS `undefined` value/type.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Undefined;
 
 /// A placeholder type to represent the JS `null` value/type.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Null;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct UndefinedLiteral {
     pub value: Undefined,
     pub original: Undefined,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct NullLiteral {
     pub value: Null,
     pub original: Null,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Hash {
     pub pairs: Vec<HashPair>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct HashPair {
     pub key: String,
-    pub value: Expression,
+    pub value: Spanned<Expression>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct StripFlags {
     pub open: bool,
     pub close: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Node {
     pub loc: SourceLocation,
     pub node: Nodes,
+    /// Set by `node_id::assign_ids`; `None` until that pass has run.
+    pub id: Option<NodeId>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Nodes {
     Program(Program),
     ElementNode(ElementNode),
@@ -281,3 +359,74 @@ pub enum Nodes {
     UndefinedLiteral(UndefinedLiteral),
     NullLiteral(NullLiteral),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loc(line: u16) -> SourceLocation {
+        SourceLocation {
+            source: None,
+            start: Position::new(line, 0).unwrap(),
+            end: Position::new(line, 1).unwrap(),
+        }
+    }
+
+    #[test]
+    fn respan_keeps_the_node_but_replaces_the_span() {
+        let spanned = respan(loc(3), 42);
+
+        assert_eq!(spanned.node, 42);
+        assert_eq!(spanned.span, loc(3));
+        assert_eq!(spanned.id, None);
+    }
+
+    #[test]
+    fn dummy_spanned_uses_a_placeholder_span() {
+        let spanned = dummy_spanned(42);
+
+        assert_eq!(spanned.node, 42);
+        assert_eq!(
+            spanned.span,
+            SourceLocation {
+                source: None,
+                start: Position::default(),
+                end: Position::default(),
+            }
+        );
+        assert_eq!(spanned.id, None);
+    }
+
+    #[test]
+    fn map_transforms_the_node_and_keeps_span_and_id() {
+        // `NodeId`'s field is private to `node_id`, so short of decoding one
+        // from serialized data, `assign_ids` is how code outside that module
+        // gets a `Some(NodeId)`; route through it to prove `map` preserves a
+        // real id rather than just the `None` every other test here starts
+        // from.
+        let program = Program {
+            body: vec![dummy_spanned(Statement::TextNode(TextNode {
+                chars: String::new(),
+            }))],
+            block_params: Vec::new(),
+        };
+        let node = crate::node_id::assign_ids(Node {
+            loc: loc(1),
+            node: Nodes::Program(program),
+            id: None,
+        });
+        let statement = match node.node {
+            Nodes::Program(program) => program.body.into_iter().next().unwrap(),
+            _ => panic!("expected a Program"),
+        };
+        let id_before = statement.id;
+        let span_before = statement.span.clone();
+        assert!(id_before.is_some());
+
+        let mapped = statement.map(|s| matches!(s, Statement::TextNode(_)));
+
+        assert!(mapped.node);
+        assert_eq!(mapped.span, span_before);
+        assert_eq!(mapped.id, id_before);
+    }
+}
@@ -0,0 +1,365 @@
+//! Turns an AST back into Glimmer/Handlebars template source -- the
+//! inverse of parsing. It can back a formatter, and together with a parser
+//! it makes a parse -> print -> parse round trip over the AST types in
+//! `nodes` possible. This crate has no parser, so its own tests compare
+//! printed output against expected source strings.
+
+use crate::nodes::*;
+
+/// Options controlling how `Printer` renders a template.
+///
+/// There's no `indent_width` here: `TextNode`s carry their surrounding
+/// whitespace verbatim from the source, so an indentation pass would be
+/// inserting whitespace the parser never produced -- exactly the kind of
+/// divergence a parse -> print -> parse round trip would catch.
+/// Pretty-printing with real indentation would need to rebuild text nodes
+/// around the inserted whitespace, which is a bigger feature than this
+/// printer -- a faithful-round-trip renderer -- takes on.
+#[derive(Clone, Debug, Default)]
+pub struct PrintOptions {
+    /// Whether to drop text nodes that are entirely whitespace.
+    pub collapse_blank_text: bool,
+}
+
+/// Renders `node` as template source using the default `PrintOptions`.
+pub fn print(node: &Node) -> String {
+    Printer::new(PrintOptions::default()).print(node)
+}
+
+pub struct Printer {
+    options: PrintOptions,
+    out: String,
+}
+
+impl Printer {
+    pub fn new(options: PrintOptions) -> Printer {
+        Printer {
+            options,
+            out: String::new(),
+        }
+    }
+
+    pub fn print(mut self, node: &Node) -> String {
+        self.node(&node.node);
+        self.out
+    }
+
+    fn node(&mut self, node: &Nodes) {
+        match node {
+            Nodes::Program(program) => self.program(program),
+            Nodes::ElementNode(element) => self.element(element),
+            Nodes::AttrNode(attr) => self.attr(attr),
+            Nodes::MustacheStatement(mustache) => self.mustache(mustache),
+            Nodes::BlockStatement(block) => self.block(block),
+            Nodes::PartialStatement(partial) => self.partial(partial),
+            Nodes::MustacheCommentStatement(comment) => self.mustache_comment(comment),
+            Nodes::CommentStatement(comment) => self.comment(comment),
+            Nodes::TextNode(text) => self.text(text),
+            Nodes::ConcatStatement(_)
+            | Nodes::ElementModifierStatement(_)
+            | Nodes::PathExpression(_)
+            | Nodes::SubExpression(_)
+            | Nodes::Hash(_)
+            | Nodes::HashPair(_)
+            | Nodes::StringLiteral(_)
+            | Nodes::BooleanLiteral(_)
+            | Nodes::NumberLiteral(_)
+            | Nodes::UndefinedLiteral(_)
+            | Nodes::NullLiteral(_) => {}
+        }
+    }
+
+    fn program(&mut self, program: &Program) {
+        for statement in &program.body {
+            self.statement(&statement.node);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::MustacheStatement(mustache) => self.mustache(mustache),
+            Statement::BlockStatement(block) => self.block(block),
+            Statement::PartialStatement(partial) => self.partial(partial),
+            Statement::MustacheComment(comment) => self.mustache_comment(comment),
+            Statement::TextNode(text) => self.text(text),
+            Statement::ElementNode(element) => self.element(element),
+        }
+    }
+
+    fn text(&mut self, text: &TextNode) {
+        if self.options.collapse_blank_text && text.chars.trim().is_empty() {
+            return;
+        }
+        self.out.push_str(&text.chars);
+    }
+
+    fn comment(&mut self, comment: &CommentStatement) {
+        self.out.push_str("<!--");
+        self.out.push_str(&comment.value);
+        self.out.push_str("-->");
+    }
+
+    fn mustache_comment(&mut self, comment: &MustacheCommentStatement) {
+        self.out.push_str("{{!--");
+        self.out.push_str(&comment.value);
+        self.out.push_str("--}}");
+    }
+
+    fn element(&mut self, element: &ElementNode) {
+        self.out.push('<');
+        self.out.push_str(&element.tag);
+
+        for attr in &element.attributes {
+            self.out.push(' ');
+            self.attr(attr);
+        }
+
+        for modifier in &element.modifiers {
+            self.out.push_str(" {{");
+            self.out.push_str(&path_source(&modifier.path));
+            self.params(&modifier.params);
+            self.hash(&modifier.hash);
+            self.out.push_str("}}");
+        }
+
+        self.block_params(&element.block_params);
+
+        if element.self_closing {
+            self.out.push_str(" />");
+            return;
+        }
+
+        self.out.push('>');
+        for child in &element.children {
+            self.statement(&child.node);
+        }
+        self.out.push_str("</");
+        self.out.push_str(&element.tag);
+        self.out.push('>');
+    }
+
+    fn attr(&mut self, attr: &AttrNode) {
+        self.out.push_str(&attr.name);
+        self.out.push_str("=\"");
+        match &attr.value {
+            AttrValue::TextNode(text) => self.out.push_str(&escape_attr_text(&text.chars)),
+            AttrValue::MustacheStatement(mustache) => self.mustache(mustache),
+            AttrValue::ConcatStatement(concat) => {
+                for part in &concat.parts {
+                    match part {
+                        ConcatParts::TextNode(text) => {
+                            self.out.push_str(&escape_attr_text(&text.chars))
+                        }
+                        ConcatParts::MustacheStatement(mustache) => self.mustache(mustache),
+                    }
+                }
+            }
+        }
+        self.out.push('"');
+    }
+
+    fn mustache(&mut self, mustache: &MustacheStatement) {
+        let (open, close) = if mustache.escaped {
+            ("{{", "}}")
+        } else {
+            ("{{{", "}}}")
+        };
+
+        self.out.push_str(open);
+        match &mustache.path {
+            MustachePath::Path(path) => self.out.push_str(&path_source(path)),
+            MustachePath::Literal(literal) => self.out.push_str(&literal_source(literal)),
+        }
+        self.params(&mustache.params);
+        self.hash(&mustache.hash);
+        self.out.push_str(close);
+    }
+
+    fn block(&mut self, block: &BlockStatement) {
+        self.out.push_str("{{#");
+        self.out.push_str(&path_source(&block.path));
+        self.params(&block.params);
+        self.hash(&block.hash);
+        self.block_params(&block.program.block_params);
+        self.out.push_str("}}");
+
+        self.program(&block.program);
+
+        if let Some(inverse) = &block.inverse {
+            self.out.push_str("{{else}}");
+            self.program(inverse);
+        }
+
+        self.out.push_str("{{/");
+        self.out.push_str(&path_source(&block.path));
+        self.out.push_str("}}");
+    }
+
+    fn partial(&mut self, partial: &PartialStatement) {
+        self.out.push_str("{{");
+        if partial.strip.open {
+            self.out.push('~');
+        }
+        self.out.push_str("> ");
+        self.out.push_str(&partial.ident);
+        self.params(&partial.params);
+        self.hash(&partial.hash);
+        if partial.strip.close {
+            self.out.push('~');
+        }
+        self.out.push_str("}}");
+    }
+
+    fn params(&mut self, params: &[Spanned<Expression>]) {
+        for param in params {
+            self.out.push(' ');
+            self.out.push_str(&expression_source(&param.node));
+        }
+    }
+
+    fn hash(&mut self, hash: &Hash) {
+        for pair in &hash.pairs {
+            self.out.push(' ');
+            self.out.push_str(&pair.key);
+            self.out.push('=');
+            self.out.push_str(&expression_source(&pair.value.node));
+        }
+    }
+
+    fn block_params(&mut self, block_params: &[String]) {
+        if block_params.is_empty() {
+            return;
+        }
+        self.out.push_str(" as |");
+        self.out.push_str(&block_params.join(" "));
+        self.out.push('|');
+    }
+}
+
+fn path_source(path: &PathExpression) -> String {
+    path.original.clone()
+}
+
+fn expression_source(expression: &Expression) -> String {
+    match expression {
+        Expression::PathExpression(path) => path_source(path),
+        Expression::Literal(literal) => literal_source(literal),
+        Expression::SubExpression(sub) => {
+            let mut printer = Printer::new(PrintOptions::default());
+            printer.out.push('(');
+            printer.out.push_str(&path_source(&sub.path));
+            printer.params(&sub.params);
+            printer.hash(&sub.hash);
+            printer.out.push(')');
+            printer.out
+        }
+    }
+}
+
+fn literal_source(literal: &Literal) -> String {
+    match literal {
+        Literal::StringLiteral(string) => format!("\"{}\"", escape_string_literal(&string.value)),
+        Literal::BooleanLiteral(boolean) => boolean.value.to_string(),
+        Literal::NumberLiteral(number) => number.value.to_string(),
+        Literal::UndefinedLiteral(_) => "undefined".to_string(),
+        Literal::NullLiteral(_) => "null".to_string(),
+    }
+}
+
+/// Escapes `\` and `"` so `value` round-trips as a double-quoted string
+/// literal param (e.g. in `{{foo "bar \"baz\""}}`).
+fn escape_string_literal(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escapes `"` so `text` round-trips inside a double-quoted HTML attribute
+/// value.
+fn escape_attr_text(text: &str) -> String {
+    text.replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::{dummy_spanned, Hash, Position, SourceLocation};
+
+    fn path(original: &str) -> PathExpression {
+        PathExpression {
+            call: None,
+            data: false,
+            original: original.to_string(),
+            this: false,
+            parts: original.split('.').map(str::to_string).collect(),
+        }
+    }
+
+    fn node(n: Nodes) -> Node {
+        Node {
+            loc: SourceLocation {
+                source: None,
+                start: Position::default(),
+                end: Position::default(),
+            },
+            node: n,
+            id: None,
+        }
+    }
+
+    #[test]
+    fn block_with_params_round_trips_the_as_pipes_clause() {
+        let item_mustache = Statement::MustacheStatement(MustacheStatement {
+            path: MustachePath::Path(path("item")),
+            params: Vec::new(),
+            hash: Hash { pairs: Vec::new() },
+            escaped: true,
+        });
+
+        let block = BlockStatement {
+            path: path("each"),
+            params: vec![dummy_spanned(Expression::PathExpression(path("items")))],
+            hash: Hash { pairs: Vec::new() },
+            program: Program {
+                body: vec![dummy_spanned(item_mustache)],
+                block_params: vec!["item".to_string()],
+            },
+            inverse: None,
+        };
+
+        let rendered = print(&node(Nodes::BlockStatement(block)));
+
+        assert_eq!(rendered, "{{#each items as |item|}}{{item}}{{/each}}");
+    }
+
+    #[test]
+    fn string_literal_params_escape_quotes_and_backslashes() {
+        let mustache = MustacheStatement {
+            path: MustachePath::Path(path("foo")),
+            params: vec![dummy_spanned(Expression::Literal(Literal::StringLiteral(
+                StringLiteral {
+                    value: "bar \"baz\" \\qux".to_string(),
+                    original: "bar \"baz\" \\qux".to_string(),
+                },
+            )))],
+            hash: Hash { pairs: Vec::new() },
+            escaped: true,
+        };
+
+        let rendered = print(&node(Nodes::MustacheStatement(mustache)));
+
+        assert_eq!(rendered, "{{foo \"bar \\\"baz\\\" \\\\qux\"}}");
+    }
+
+    #[test]
+    fn attr_text_escapes_embedded_quotes() {
+        let attr = AttrNode {
+            name: "title".to_string(),
+            value: AttrValue::TextNode(TextNode {
+                chars: "say \"hi\"".to_string(),
+            }),
+        };
+
+        let rendered = print(&node(Nodes::AttrNode(attr)));
+
+        assert_eq!(rendered, "title=\"say &quot;hi&quot;\"");
+    }
+}
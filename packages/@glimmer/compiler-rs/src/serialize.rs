@@ -0,0 +1,108 @@
+//! Wire format for the AST, so a template can be compiled once and the
+//! parsed result shipped as bytes instead of re-parsed on every load.
+//!
+//! `encode`/`decode` use a compact binary encoding; the `_json` variants
+//! exist purely for debugging precompiled templates by hand -- they're
+//! human-readable, at the cost of being slower and larger on the wire.
+
+use std::error;
+use std::fmt;
+
+use crate::nodes::Node;
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Binary(bincode::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Binary(err) => write!(f, "failed to decode template: {}", err),
+            DecodeError::Json(err) => write!(f, "failed to decode template: {}", err),
+        }
+    }
+}
+
+impl error::Error for DecodeError {}
+
+impl From<bincode::Error> for DecodeError {
+    fn from(err: bincode::Error) -> DecodeError {
+        DecodeError::Binary(err)
+    }
+}
+
+impl From<serde_json::Error> for DecodeError {
+    fn from(err: serde_json::Error) -> DecodeError {
+        DecodeError::Json(err)
+    }
+}
+
+/// Encodes `node` into the compact binary wire format.
+pub fn encode(node: &Node) -> Vec<u8> {
+    bincode::serialize(node).expect("AST serialization should never fail")
+}
+
+/// Decodes a `Node` previously produced by `encode`.
+pub fn decode(bytes: &[u8]) -> Result<Node, DecodeError> {
+    Ok(bincode::deserialize(bytes)?)
+}
+
+/// Encodes `node` as JSON, for debugging precompiled templates by hand.
+pub fn encode_json(node: &Node) -> Result<String, DecodeError> {
+    Ok(serde_json::to_string(node)?)
+}
+
+/// Decodes a `Node` previously produced by `encode_json`.
+pub fn decode_json(json: &str) -> Result<Node, DecodeError> {
+    Ok(serde_json::from_str(json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::{NumberLiteral, Nodes, Position, SourceLocation};
+
+    fn number_node(value: f64, original: f64) -> Node {
+        Node {
+            loc: SourceLocation {
+                source: None,
+                start: Position::default(),
+                end: Position::default(),
+            },
+            node: Nodes::NumberLiteral(NumberLiteral { value, original }),
+            id: None,
+        }
+    }
+
+    fn number_literal(node: &Node) -> &NumberLiteral {
+        match &node.node {
+            Nodes::NumberLiteral(number) => number,
+            _ => panic!("expected a NumberLiteral node"),
+        }
+    }
+
+    #[test]
+    fn number_literal_round_trips_nan_and_infinity_through_binary() {
+        let node = number_node(f64::NAN, f64::NEG_INFINITY);
+        let decoded = decode(&encode(&node)).expect("decode should succeed");
+
+        let original = number_literal(&node);
+        let decoded = number_literal(&decoded);
+        assert!(decoded.value.is_nan());
+        assert_eq!(decoded.original.to_bits(), original.original.to_bits());
+    }
+
+    #[test]
+    fn number_literal_round_trips_nan_and_infinity_through_json() {
+        let node = number_node(f64::NAN, f64::INFINITY);
+        let json = encode_json(&node).expect("encode_json should succeed");
+        let decoded = decode_json(&json).expect("decode_json should succeed");
+
+        let original = number_literal(&node);
+        let decoded = number_literal(&decoded);
+        assert_eq!(decoded.value.to_bits(), original.value.to_bits());
+        assert_eq!(decoded.original.to_bits(), original.original.to_bits());
+    }
+}
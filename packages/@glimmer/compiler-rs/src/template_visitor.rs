@@ -1,6 +1,63 @@
 use std::borrow::ToOwned;
 use std::collections::HashMap;
 
+use crate::nodes as ast;
+use crate::visit::{self, Visitor};
+
+/// An interned symbol name.
+///
+/// Comparing and storing names as `Symbol`s rather than `String`s turns the
+/// `contains`/`position` scans that used to walk `Vec<String>` into plain
+/// integer comparisons, and means a name is copied only when it is first
+/// interned (once into the string list, once as the lookup key) rather than
+/// every time it is looked up.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Symbol(u32);
+
+/// Interns symbol names so that every `SymbolTable` frame can share a single
+/// namespace instead of each holding its own copies of the same strings.
+///
+/// A `SymbolInterner` is owned by the `TemplateVisitor` and threaded through
+/// to the `SymbolTable` methods that need to mint or resolve names; the
+/// tables themselves only ever see the resulting `Symbol`s.
+pub struct SymbolInterner {
+    strings: Vec<String>,
+    ids: Dict<u32>,
+}
+
+impl Default for SymbolInterner {
+    fn default() -> SymbolInterner {
+        SymbolInterner::new()
+    }
+}
+
+impl SymbolInterner {
+    pub fn new() -> SymbolInterner {
+        SymbolInterner {
+            strings: Vec::new(),
+            ids: Dict::new(),
+        }
+    }
+
+    /// Returns the `Symbol` for `name`, interning it if this is the first
+    /// time it has been seen.
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&id) = self.ids.get(name) {
+            return Symbol(id);
+        }
+
+        let id = self.strings.len() as u32;
+        self.strings.push(name.to_owned());
+        self.ids.insert(name.to_owned(), id);
+        Symbol(id)
+    }
+
+    /// Resolves a `Symbol` back to the name it was interned from.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.strings[symbol.0 as usize]
+    }
+}
+
 #[derive(Copy, Clone)]
 pub enum Action {
     StartProgram,
@@ -22,127 +79,6 @@ pub mod core {
     pub type EvalInfo = Vec<usize>;
 }
 
-// Placeholder -- should be extern?
-pub mod ast {
-    #[derive(PartialEq)]
-    pub struct Program;
-
-    #[derive(Clone, PartialEq)]
-    pub struct ConcatStatement;
-
-    #[derive(Clone, PartialEq)]
-    pub enum AttrValue {
-        TextNode(TextNode),
-        MustacheStatement(MustacheStatement),
-        ConcatStatement(ConcatStatement),
-    }
-
-    #[derive(Clone, PartialEq)]
-    pub struct AttrNode {
-        pub name: String,
-        pub value: AttrValue,
-    }
-
-    #[derive(Clone, PartialEq)]
-    pub struct ElementModifierStatement;
-
-    #[derive(Clone, PartialEq)]
-    pub struct MustacheStatement;
-
-    #[derive(Clone, PartialEq)]
-    pub struct BlockStatement;
-
-    #[derive(Clone, PartialEq)]
-    pub struct PartialStatement;
-
-    #[derive(Clone, PartialEq)]
-    pub struct TextNode {
-        pub chars: String,
-    }
-
-    #[derive(Clone, PartialEq)]
-    pub struct CommentStatement {
-        value: String,
-    }
-
-    #[derive(Clone, PartialEq)]
-    pub struct MustacheCommentStatement {
-        value: String,
-    }
-
-    #[derive(Clone, PartialEq)]
-    pub struct PathExpression;
-
-    #[derive(Clone, PartialEq)]
-    pub struct SubExpression;
-
-    #[derive(Clone, PartialEq)]
-    pub struct Hash;
-
-    #[derive(Clone, PartialEq)]
-    pub struct HashPair;
-
-    #[derive(Clone, PartialEq)]
-    pub struct StringLiteral;
-
-    #[derive(Clone, PartialEq)]
-    pub struct BooleanLiteral;
-
-    #[derive(Clone, PartialEq)]
-    pub struct NumberLiteral;
-
-    #[derive(Clone, PartialEq)]
-    pub struct UndefinedLiteral;
-
-    #[derive(Clone, PartialEq)]
-    pub struct NullLiteral;
-
-    #[derive(Clone, PartialEq)]
-    pub struct ElementNode {
-        tag: String,
-        attributes: Vec<AttrNode>,
-        block_params: Vec<String>,
-        modifiers: Vec<ElementModifierStatement>,
-        comments: Vec<MustacheCommentStatement>,
-        children: Vec<Statement>,
-    }
-
-    #[derive(Clone, PartialEq)]
-    pub enum Statement {
-        MustacheStatement(MustacheStatement),
-        BlockStatement(BlockStatement),
-        PartialStatement(PartialStatement),
-        MustacheCommentStatement(MustacheCommentStatement),
-        CommentStatement(CommentStatement),
-        TextNode(TextNode),
-        ElementNode(ElementNode),
-    }
-
-    #[derive(PartialEq)]
-    pub enum Node {
-        Program(Program),
-        ElementNode(ElementNode),
-        AttrNode(AttrNode),
-        TextNode(TextNode),
-        MustacheStatement(MustacheStatement),
-        BlockStatement(BlockStatement),
-        PartialStatement(PartialStatement),
-        ConcatStatement(ConcatStatement),
-        MustacheCommentStatement(MustacheCommentStatement),
-        ElementModifierStatement(ElementModifierStatement),
-        CommentStatement(CommentStatement),
-        PathExpression(PathExpression),
-        SubExpression(SubExpression),
-        Hash(Hash),
-        HashPair(HashPair),
-        StringLiteral(StringLiteral),
-        BooleanLiteral(BooleanLiteral),
-        NumberLiteral(NumberLiteral),
-        UndefinedLiteral(UndefinedLiteral),
-        NullLiteral(NullLiteral),
-    }
-}
-
 pub trait SymbolTable {
     fn top() -> ProgramSymbolTable
     where
@@ -151,32 +87,37 @@ pub trait SymbolTable {
         ProgramSymbolTable::new()
     }
 
-    fn has(&self, name: &str) -> bool;
-    fn get(&self, name: &str) -> usize;
+    fn has(&self, name: Symbol) -> bool;
+    fn get(&self, name: Symbol) -> usize;
 
-    fn get_locals_map(&self) -> Dict<usize>;
+    fn get_locals_map(&self, interner: &SymbolInterner) -> Dict<usize>;
     fn get_eval_info(&self) -> core::EvalInfo;
 
-    fn allocate_named(&mut self, name: &str) -> usize;
-    fn allocate_block(&mut self, name: &str) -> usize;
-    fn allocate(&mut self, identifier: &str) -> usize;
+    fn allocate_named(&mut self, interner: &mut SymbolInterner, name: &str) -> usize;
+    fn allocate_block(&mut self, interner: &mut SymbolInterner, name: &str) -> usize;
+    fn allocate(&mut self, identifier: Symbol) -> usize;
 
-    fn child(&mut self, locals: Vec<String>) -> BlockSymbolTable
+    fn child(
+        &mut self,
+        interner: &mut SymbolInterner,
+        locals: Vec<String>,
+    ) -> BlockSymbolTable<'_>
     where
         Self: Sized,
     {
-        let symbols: Vec<usize> = locals.iter().map(|name| self.allocate(&name)).collect();
+        let symbols: Vec<Symbol> = locals.iter().map(|name| interner.intern(name)).collect();
+        let slots: Vec<usize> = symbols.iter().map(|&symbol| self.allocate(symbol)).collect();
 
-        return BlockSymbolTable::new(self, &locals, symbols);
+        BlockSymbolTable::new(self, symbols, slots)
     }
 }
 
 pub struct ProgramSymbolTable {
-    pub symbols: Vec<String>,
+    pub symbols: Vec<Symbol>,
 
     size: usize,
-    named: Dict<usize>,
-    blocks: Dict<usize>,
+    named: HashMap<Symbol, usize>,
+    blocks: HashMap<Symbol, usize>,
 }
 
 impl ProgramSymbolTable {
@@ -184,22 +125,22 @@ impl ProgramSymbolTable {
         ProgramSymbolTable {
             symbols: Vec::new(),
             size: 1,
-            named: Dict::new(),
-            blocks: Dict::new(),
+            named: HashMap::new(),
+            blocks: HashMap::new(),
         }
     }
 }
 
 impl SymbolTable for ProgramSymbolTable {
-    fn has(&self, _name: &str) -> bool {
+    fn has(&self, _name: Symbol) -> bool {
         false
     }
 
-    fn get(&self, _name: &str) -> usize {
+    fn get(&self, _name: Symbol) -> usize {
         unreachable!()
     }
 
-    fn get_locals_map(&self) -> Dict<usize> {
+    fn get_locals_map(&self, _interner: &SymbolInterner) -> Dict<usize> {
         Dict::new()
     }
 
@@ -207,109 +148,105 @@ impl SymbolTable for ProgramSymbolTable {
         core::EvalInfo::new()
     }
 
-    // This is essentially a direct transcription of the version in TypeScript,
-    // but it doesn't actually make much sense to me in even the medium-term in
-    // Rust, for the simple reason that the "allocation" here is allocating a
-    // string instead of just storing an actual reference. (Something like this
-    // still might make sense if we want to avoid hairy lifetimes, but given
-    // that's Rust's strong suit...)
-    fn allocate_named(&mut self, name: &str) -> usize {
-        let named = self.named.get(name).map(ToOwned::to_owned);
+    // Interning `name` once up front, and storing only the resulting
+    // `Symbol` from here on, is what replaces the old `to_owned()`-per-lookup
+    // approach this table used to need to make the borrow checker happy.
+    fn allocate_named(&mut self, interner: &mut SymbolInterner, name: &str) -> usize {
+        let symbol = interner.intern(name);
+        let named = self.named.get(&symbol).map(ToOwned::to_owned);
         match named {
             Some(named) => named,
             None => {
-                let named = self.allocate(name);
-                self.named.insert(name.to_owned(), named);
+                let named = self.allocate(symbol);
+                self.named.insert(symbol, named);
                 named
             }
         }
     }
 
-    fn allocate_block(&mut self, name: &str) -> usize {
-        let block = self.blocks.get(name).map(ToOwned::to_owned);
+    fn allocate_block(&mut self, interner: &mut SymbolInterner, name: &str) -> usize {
+        let symbol = interner.intern(name);
+        let block = self.blocks.get(&symbol).map(ToOwned::to_owned);
         match block {
             Some(block) => block.to_owned(),
             None => {
-                let block = self.allocate(&format!("&{}", name));
-                self.blocks.insert(name.to_owned(), block);
+                let block_symbol = interner.intern(&format!("&{}", name));
+                let block = self.allocate(block_symbol);
+                self.blocks.insert(symbol, block);
                 block
             }
         }
     }
 
-    fn allocate(&mut self, identifier: &str) -> usize {
-        self.symbols.push(identifier.to_owned());
+    fn allocate(&mut self, identifier: Symbol) -> usize {
+        self.symbols.push(identifier);
         self.size += 1;
         self.size
     }
 }
 
 pub struct BlockSymbolTable<'p> {
-    parent: &'p mut SymbolTable,
-    pub symbols: Vec<String>,
+    parent: &'p mut dyn SymbolTable,
+    pub symbols: Vec<Symbol>,
     pub slots: Vec<usize>,
 }
 
 impl<'p> BlockSymbolTable<'p> {
     fn new(
-        parent: &'p mut SymbolTable,
-        symbols: &Vec<String>,
+        parent: &'p mut dyn SymbolTable,
+        symbols: Vec<Symbol>,
         slots: Vec<usize>,
     ) -> BlockSymbolTable<'p> {
         BlockSymbolTable {
             parent,
-            symbols: symbols.to_vec(),
-            slots: slots.to_vec(),
+            symbols,
+            slots,
         }
     }
 }
 
 impl<'p> SymbolTable for BlockSymbolTable<'p> {
-    fn has(&self, name: &str) -> bool {
-        // TODO: this is *dumb*. Generally points to utility of `Vec<&str>`, I
-        // suspect, but will need to see how lifetimes play out.
-        self.symbols.contains(&name.to_owned()) || self.parent.has(name)
+    fn has(&self, name: Symbol) -> bool {
+        self.symbols.contains(&name) || self.parent.has(name)
     }
 
-    // This implementation is garbage. I hate it. However, it is equivalent to
-    // the TS implementation, so it's a reasonable starting point. It would be
-    // nice not to have to maintain slots and symbols independently, of course.
-    fn get(&self, name: &str) -> usize {
-        let slot = self.symbols.iter().position(|symbol| symbol == name);
+    // Now that `symbols` holds `Symbol`s instead of `String`s, the scan for
+    // `name` compares integers rather than strings before the slot lookup.
+    fn get(&self, name: Symbol) -> usize {
+        let slot = self.symbols.iter().position(|&symbol| symbol == name);
         match slot {
-            Some(slot) => self.slots
-                .iter()
-                .nth(slot)
+            Some(slot) => self
+                .slots
+                .get(slot)
                 .expect("nth slot and symbol position should play nice")
                 .to_owned(),
             None => self.parent.get(name),
         }
     }
 
-    fn get_locals_map(&self) -> Dict<usize> {
-        let mut dict = self.parent.get_locals_map();
-        self.symbols.iter().for_each(|symbol| {
-            dict.insert(symbol.to_owned(), self.get(&symbol));
+    fn get_locals_map(&self, interner: &SymbolInterner) -> Dict<usize> {
+        let mut dict = self.parent.get_locals_map(interner);
+        self.symbols.iter().for_each(|&symbol| {
+            dict.insert(interner.resolve(symbol).to_owned(), self.get(symbol));
         });
         dict
     }
 
     fn get_eval_info(&self) -> core::EvalInfo {
-        self.get_locals_map()
-            .values()
-            .map(ToOwned::to_owned)
-            .collect()
+        let mut info = self.parent.get_eval_info();
+        info.extend(self.symbols.iter().map(|&symbol| self.get(symbol)));
+        info
     }
 
-    fn allocate_named(&mut self, name: &str) -> usize {
-        self.parent.allocate_named(name)
+    fn allocate_named(&mut self, interner: &mut SymbolInterner, name: &str) -> usize {
+        self.parent.allocate_named(interner, name)
     }
 
-    fn allocate_block(&mut self, name: &str) -> usize {
-        self.parent.allocate_block(name)
+    fn allocate_block(&mut self, interner: &mut SymbolInterner, name: &str) -> usize {
+        self.parent.allocate_block(interner, name)
     }
 
-    fn allocate(&mut self, identifier: &str) -> usize {
+    fn allocate(&mut self, identifier: Symbol) -> usize {
         self.parent.allocate(identifier)
     }
 }
@@ -319,14 +256,20 @@ pub struct JSObject;
 
 pub struct Frame {
     pub parent_node: Option<JSObject>,
-    pub children: Option<Vec<ast::Node>>,
+    pub children: Option<Vec<ast::Statement>>,
     pub child_index: Option<usize>,
     pub child_count: Option<usize>,
     pub child_template_count: usize,
     pub mustache_count: usize,
     pub actions: Vec<Action>,
     pub blank_child_text_nodes: Option<Vec<isize>>,
-    pub symbols: Option<Box<SymbolTable>>,
+    pub symbols: Option<Box<dyn SymbolTable>>,
+}
+
+impl Default for Frame {
+    fn default() -> Frame {
+        Frame::new()
+    }
 }
 
 impl Frame {
@@ -346,16 +289,22 @@ impl Frame {
 }
 
 pub struct TemplateVisitor {
-    current_frame_actual: Option<Frame>,
+    pub interner: SymbolInterner,
     frame_stack: Vec<Frame>,
     pub actions: Vec<Action>,
     program_depth: isize, // TODO: might actually be better as an `Option` in Rust?
 }
 
+impl Default for TemplateVisitor {
+    fn default() -> TemplateVisitor {
+        TemplateVisitor::new()
+    }
+}
+
 impl TemplateVisitor {
     pub fn new() -> TemplateVisitor {
         TemplateVisitor {
-            current_frame_actual: None,
+            interner: SymbolInterner::new(),
             frame_stack: Vec::new(),
             program_depth: -1,
             actions: Vec::new(),
@@ -363,88 +312,109 @@ impl TemplateVisitor {
     }
 
     pub fn current_frame(&mut self) -> &mut Frame {
-        self.current_frame_actual.as_mut().expect("Expected a current frame")
+        self.frame_stack.last_mut().expect("Expected a current frame")
     }
 
-    pub fn visit(&mut self, node: ast::Node) {
-        match node {
-            ast::Node::Program(program) => self.program(program),
-            ast::Node::ElementNode(element) => self.element_node(element),
-            ast::Node::AttrNode(attr) => self.attr_node(attr),
-            ast::Node::TextNode(text) => self.text_node(text),
-            ast::Node::BlockStatement(block) => self.block_statement(block),
-            ast::Node::PartialStatement(partial) => self.partial_statement(partial),
-            ast::Node::CommentStatement(comment) => self.comment_statement(comment),
-            ast::Node::MustacheCommentStatement(mustache_comment) => {
-                self.mustache_comment_statement(mustache_comment)
-            }
-            ast::Node::MustacheStatement(mustache_statement) => {
-                self.mustache_statement(mustache_statement)
-            }
-            _ => unimplemented!(),
-        }
+    pub fn visit(&mut self, node: &ast::Node) {
+        visit::walk_node(self, node)
+    }
+
+    fn get_current_frame(&self) -> Option<&Frame> {
+        self.frame_stack.last()
+    }
+
+    fn push_frame(&mut self) -> &Frame {
+        let frame = Frame::new();
+        self.frame_stack.push(frame);
+        self.get_current_frame().expect("Just pushed frame, so it must be present")
     }
 
-    pub fn program(&mut self, program: ast::Program) {
-        unimplemented!()
-        // self.program_depth += 1;
+    fn pop_frame(&mut self) -> Option<Frame> {
+        self.frame_stack.pop()
+    }
+}
+
+// The frame-pushing and `blank_child_text_nodes` bookkeeping this visitor
+// needs now rides on the generic `Visitor` trait: only the handful of node
+// kinds that actually open/close a frame or a DOM node are overridden here,
+// and the default methods (backed by the `walk_*` functions) take care of
+// recursing into everything else.
+impl Visitor for TemplateVisitor {
+    fn visit_program(&mut self, program: &ast::Program) {
+        self.program_depth += 1;
+
+        self.push_frame();
+        {
+            let frame = self.current_frame();
+            // `Frame` only needs the statements themselves to answer "is this
+            // child blank text?" -- it isn't in the business of diagnostics,
+            // so spans are dropped at this boundary.
+            frame.children = Some(program.body.iter().map(|s| s.node.clone()).collect());
+            frame.child_count = Some(program.body.len());
+            frame.blank_child_text_nodes = Some(Vec::new());
+        }
+
+        self.actions.push(if self.program_depth == 0 {
+            Action::StartProgram
+        } else {
+            Action::StartBlock
+        });
+
+        visit::walk_program(self, program);
+
+        self.actions.push(if self.program_depth == 0 {
+            Action::EndProgram
+        } else {
+            Action::EndBlock
+        });
 
-        // let parentFrame = self.get_current_frame();
-        // let programFrame = self.push_frame();
+        self.pop_frame();
+        self.program_depth -= 1;
     }
 
-    pub fn element_node(&self, element: ast::ElementNode) {
-        unimplemented!()
+    fn visit_element(&mut self, element: &ast::ElementNode) {
+        self.actions.push(Action::OpenElement);
+        visit::walk_element(self, element);
+        self.actions.push(Action::CloseElement);
     }
 
-    pub fn attr_node(&mut self, attr: ast::AttrNode) {
+    fn visit_attr(&mut self, attr: &ast::AttrNode) {
         match attr.value {
             ast::AttrValue::TextNode(_) => (),
             _ => self.current_frame().mustache_count += 1,
         }
+        visit::walk_attr(self, attr);
     }
 
-    pub fn text_node(&mut self, text: ast::TextNode) {
+    fn visit_text(&mut self, text: &ast::TextNode) {
+        self.actions.push(Action::Text);
+
         let frame = self.current_frame();
         if text.chars.is_empty() {
             let nodes = frame.blank_child_text_nodes.as_mut().expect("frame must have child nodes");
             let children = frame.children.as_ref().expect("frame must have children");
-            nodes.push(dom_index_of(children, DOMNode::TextNode(text)));
+            nodes.push(dom_index_of(children, DOMNode::TextNode(text.to_owned())));
         }
     }
 
-    pub fn block_statement(&self, node: ast::BlockStatement) {
-        unimplemented!()
-    }
-
-    pub fn partial_statement(&self, node: ast::PartialStatement) {
-        unimplemented!()
+    fn visit_block(&mut self, block: &ast::BlockStatement) {
+        self.actions.push(Action::Block);
+        visit::walk_block(self, block);
     }
 
-    pub fn comment_statement(&self, node: ast::CommentStatement) {
-        unimplemented!()
+    fn visit_mustache(&mut self, mustache: &ast::MustacheStatement) {
+        self.actions.push(Action::Mustache);
+        visit::walk_mustache(self, mustache);
     }
 
-    pub fn mustache_comment_statement(&self, node: ast::MustacheCommentStatement) {
-        unimplemented!()
+    fn visit_comment(&mut self, comment: &ast::CommentStatement) {
+        let _ = comment;
+        self.actions.push(Action::Comment);
     }
 
-    pub fn mustache_statement(&self, node: ast::MustacheStatement) {
-        unimplemented!()
-    }
-
-    fn get_current_frame(&self) -> Option<&Frame> {
-        self.frame_stack.last()
-    }
-
-    fn push_frame(&mut self) -> &Frame {
-        let frame = Frame::new();
-        self.frame_stack.push(frame);
-        self.get_current_frame().expect("Just pushed frame, so it must be present")
-    }
-
-    fn pop_Frame(&mut self) -> Option<Frame> {
-        self.frame_stack.pop()
+    fn visit_mustache_comment(&mut self, comment: &ast::MustacheCommentStatement) {
+        let _ = comment;
+        self.actions.push(Action::Comment);
     }
 }
 
@@ -455,36 +425,36 @@ enum DOMNode {
 }
 
 trait IntoSafe<T> : Sized {
-    fn into_safe(&self) -> Option<T>;
+    fn as_safe(&self) -> Option<T>;
 }
 
-impl IntoSafe<DOMNode> for ast::Node {
-    fn into_safe(&self) -> Option<DOMNode> {
+impl IntoSafe<DOMNode> for ast::Statement {
+    fn as_safe(&self) -> Option<DOMNode> {
         match self {
-            ast::Node::TextNode(tn) => Some(DOMNode::TextNode(tn.to_owned())),
-            ast::Node::ElementNode(en) => Some(DOMNode::ElementNode(en.clone())),
+            ast::Statement::TextNode(tn) => Some(DOMNode::TextNode(tn.to_owned())),
+            ast::Statement::ElementNode(en) => Some(DOMNode::ElementNode(en.clone())),
             _ => None,
         }
     }
 }
 
-impl PartialEq<DOMNode> for ast::Node {
+impl PartialEq<DOMNode> for ast::Statement {
     fn eq(&self, other: &DOMNode) -> bool {
-        match self.into_safe() {
+        match self.as_safe() {
             Some(dn) => other == &dn,
             None => false,
         }
     }
 }
 
-fn dom_index_of(nodes: &Vec<ast::Node>, dom_node: DOMNode) -> isize {
+fn dom_index_of(nodes: &[ast::Statement], dom_node: DOMNode) -> isize {
     let mut index = -1;
 
     for i in 0..nodes.len() {
         let node = nodes.get(i).expect("Only getting nodes within vec bounds");
 
         match node {
-            ast::Node::TextNode(_) | ast::Node::ElementNode(_) => index += 1,
+            ast::Statement::TextNode(_) | ast::Statement::ElementNode(_) => index += 1,
             _ => continue,
         }
 
@@ -495,3 +465,34 @@ fn dom_index_of(nodes: &Vec<ast::Node>, dom_node: DOMNode) -> isize {
 
     -1
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interner_resolves_interned_names_and_dedupes_repeats() {
+        let mut interner = SymbolInterner::new();
+        let a = interner.intern("a");
+        let b = interner.intern("b");
+        let a_again = interner.intern("a");
+
+        assert_eq!(a, a_again);
+        assert_ne!(a, b);
+        assert_eq!(interner.resolve(a), "a");
+        assert_eq!(interner.resolve(b), "b");
+    }
+
+    #[test]
+    fn get_eval_info_includes_locals_from_every_enclosing_block() {
+        let mut interner = SymbolInterner::new();
+        let mut program = ProgramSymbolTable::new();
+        let mut outer = program.child(&mut interner, vec!["outer".to_string()]);
+        let outer_slot = outer.slots[0];
+
+        let inner = outer.child(&mut interner, vec!["inner".to_string()]);
+        let inner_slot = inner.slots[0];
+
+        assert_eq!(inner.get_eval_info(), vec![outer_slot, inner_slot]);
+    }
+}
@@ -0,0 +1,430 @@
+//! Generic traversal over the AST in `nodes`, modeled on rustc's
+//! `Visitor`/`MutVisitor` split: `Visitor` walks the tree read-only, while
+//! `Folder` rebuilds it, letting an implementor rewrite only the node kinds
+//! it cares about.
+
+use crate::nodes::*;
+
+/// A read-only traversal over the AST.
+///
+/// Every method has a default implementation that delegates to the matching
+/// `walk_*` free function, so an implementor only needs to override the
+/// handful of node kinds it actually cares about -- everything else keeps
+/// recursing into its children on its own.
+pub trait Visitor: Sized {
+    fn visit_node(&mut self, node: &Node) {
+        walk_node(self, node);
+    }
+
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_statement(&mut self, statement: &Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_element(&mut self, element: &ElementNode) {
+        walk_element(self, element);
+    }
+
+    fn visit_attr(&mut self, attr: &AttrNode) {
+        walk_attr(self, attr);
+    }
+
+    fn visit_block(&mut self, block: &BlockStatement) {
+        walk_block(self, block);
+    }
+
+    fn visit_mustache(&mut self, mustache: &MustacheStatement) {
+        walk_mustache(self, mustache);
+    }
+
+    fn visit_modifier(&mut self, modifier: &ElementModifierStatement) {
+        walk_modifier(self, modifier);
+    }
+
+    fn visit_partial(&mut self, partial: &PartialStatement) {
+        walk_partial(self, partial);
+    }
+
+    fn visit_comment(&mut self, comment: &CommentStatement) {
+        let _ = comment;
+    }
+
+    fn visit_mustache_comment(&mut self, comment: &MustacheCommentStatement) {
+        let _ = comment;
+    }
+
+    fn visit_text(&mut self, text: &TextNode) {
+        let _ = text;
+    }
+
+    fn visit_expression(&mut self, expression: &Expression) {
+        walk_expression(self, expression);
+    }
+}
+
+pub fn walk_node<V: Visitor>(visitor: &mut V, node: &Node) {
+    match &node.node {
+        Nodes::Program(program) => visitor.visit_program(program),
+        Nodes::ElementNode(element) => visitor.visit_element(element),
+        Nodes::AttrNode(attr) => visitor.visit_attr(attr),
+        Nodes::TextNode(text) => visitor.visit_text(text),
+        Nodes::MustacheStatement(mustache) => visitor.visit_mustache(mustache),
+        Nodes::BlockStatement(block) => visitor.visit_block(block),
+        Nodes::PartialStatement(partial) => visitor.visit_partial(partial),
+        Nodes::MustacheCommentStatement(comment) => visitor.visit_mustache_comment(comment),
+        Nodes::CommentStatement(comment) => visitor.visit_comment(comment),
+        Nodes::ElementModifierStatement(modifier) => visitor.visit_modifier(modifier),
+        Nodes::PathExpression(_)
+        | Nodes::SubExpression(_)
+        | Nodes::Hash(_)
+        | Nodes::HashPair(_)
+        | Nodes::StringLiteral(_)
+        | Nodes::BooleanLiteral(_)
+        | Nodes::NumberLiteral(_)
+        | Nodes::UndefinedLiteral(_)
+        | Nodes::NullLiteral(_)
+        | Nodes::ConcatStatement(_) => {}
+    }
+}
+
+pub fn walk_program<V: Visitor>(visitor: &mut V, program: &Program) {
+    for statement in &program.body {
+        visitor.visit_statement(&statement.node);
+    }
+}
+
+pub fn walk_statement<V: Visitor>(visitor: &mut V, statement: &Statement) {
+    match statement {
+        Statement::MustacheStatement(mustache) => visitor.visit_mustache(mustache),
+        Statement::BlockStatement(block) => visitor.visit_block(block),
+        Statement::PartialStatement(partial) => visitor.visit_partial(partial),
+        Statement::MustacheComment(comment) => visitor.visit_mustache_comment(comment),
+        Statement::TextNode(text) => visitor.visit_text(text),
+        Statement::ElementNode(element) => visitor.visit_element(element),
+    }
+}
+
+pub fn walk_element<V: Visitor>(visitor: &mut V, element: &ElementNode) {
+    for attr in &element.attributes {
+        visitor.visit_attr(attr);
+    }
+    for modifier in &element.modifiers {
+        visitor.visit_modifier(modifier);
+    }
+    for comment in &element.comments {
+        visitor.visit_mustache_comment(comment);
+    }
+    for child in &element.children {
+        visitor.visit_statement(&child.node);
+    }
+}
+
+pub fn walk_attr<V: Visitor>(visitor: &mut V, attr: &AttrNode) {
+    match &attr.value {
+        AttrValue::TextNode(text) => visitor.visit_text(text),
+        AttrValue::MustacheStatement(mustache) => visitor.visit_mustache(mustache),
+        AttrValue::ConcatStatement(concat) => {
+            for part in &concat.parts {
+                match part {
+                    ConcatParts::TextNode(text) => visitor.visit_text(text),
+                    ConcatParts::MustacheStatement(mustache) => visitor.visit_mustache(mustache),
+                }
+            }
+        }
+    }
+}
+
+pub fn walk_block<V: Visitor>(visitor: &mut V, block: &BlockStatement) {
+    for param in &block.params {
+        visitor.visit_expression(&param.node);
+    }
+    walk_hash(visitor, &block.hash);
+    visitor.visit_program(&block.program);
+    if let Some(inverse) = &block.inverse {
+        visitor.visit_program(inverse);
+    }
+}
+
+pub fn walk_mustache<V: Visitor>(visitor: &mut V, mustache: &MustacheStatement) {
+    for param in &mustache.params {
+        visitor.visit_expression(&param.node);
+    }
+    walk_hash(visitor, &mustache.hash);
+}
+
+pub fn walk_modifier<V: Visitor>(visitor: &mut V, modifier: &ElementModifierStatement) {
+    for param in &modifier.params {
+        visitor.visit_expression(&param.node);
+    }
+    walk_hash(visitor, &modifier.hash);
+}
+
+pub fn walk_partial<V: Visitor>(visitor: &mut V, partial: &PartialStatement) {
+    for param in &partial.params {
+        visitor.visit_expression(&param.node);
+    }
+    walk_hash(visitor, &partial.hash);
+}
+
+pub fn walk_expression<V: Visitor>(visitor: &mut V, expression: &Expression) {
+    if let Expression::SubExpression(sub) = expression {
+        for param in &sub.params {
+            visitor.visit_expression(&param.node);
+        }
+        walk_hash(visitor, &sub.hash);
+    }
+}
+
+pub fn walk_hash<V: Visitor>(visitor: &mut V, hash: &Hash) {
+    for pair in &hash.pairs {
+        visitor.visit_expression(&pair.value.node);
+    }
+}
+
+/// A traversal that rebuilds the tree, letting each step rewrite the node it
+/// is given before (or instead of) recursing into its children.
+///
+/// The `noop_fold_*` helpers perform the identity transformation -- recurse
+/// and rebuild the same shape -- so a `Folder` only needs to implement the
+/// `fold_*` methods for the node kinds it actually wants to change.
+pub trait Folder: Sized {
+    fn fold_program(&mut self, program: Program) -> Program {
+        noop_fold_program(self, program)
+    }
+
+    fn fold_statement(&mut self, statement: Statement) -> Statement {
+        noop_fold_statement(self, statement)
+    }
+
+    fn fold_element(&mut self, element: ElementNode) -> ElementNode {
+        noop_fold_element(self, element)
+    }
+
+    fn fold_attr(&mut self, attr: AttrNode) -> AttrNode {
+        noop_fold_attr(self, attr)
+    }
+
+    fn fold_block(&mut self, block: BlockStatement) -> BlockStatement {
+        noop_fold_block(self, block)
+    }
+
+    fn fold_mustache(&mut self, mustache: MustacheStatement) -> MustacheStatement {
+        noop_fold_mustache(self, mustache)
+    }
+
+    fn fold_modifier(&mut self, modifier: ElementModifierStatement) -> ElementModifierStatement {
+        noop_fold_modifier(self, modifier)
+    }
+
+    fn fold_partial(&mut self, partial: PartialStatement) -> PartialStatement {
+        noop_fold_partial(self, partial)
+    }
+
+    fn fold_comment(&mut self, comment: CommentStatement) -> CommentStatement {
+        comment
+    }
+
+    fn fold_mustache_comment(
+        &mut self,
+        comment: MustacheCommentStatement,
+    ) -> MustacheCommentStatement {
+        comment
+    }
+
+    fn fold_text(&mut self, text: TextNode) -> TextNode {
+        text
+    }
+
+    fn fold_expression(&mut self, expression: Expression) -> Expression {
+        noop_fold_expression(self, expression)
+    }
+
+    /// Folds a `Statement` still wrapped in its `Spanned`, in case an
+    /// implementor needs to touch the wrapper itself (e.g. to stamp a
+    /// `NodeId` onto it) rather than just the `Statement` inside.
+    fn fold_spanned_statement(&mut self, statement: Spanned<Statement>) -> Spanned<Statement> {
+        statement.map(|s| self.fold_statement(s))
+    }
+
+    /// The `Expression` counterpart to `fold_spanned_statement`.
+    fn fold_spanned_expression(&mut self, expression: Spanned<Expression>) -> Spanned<Expression> {
+        expression.map(|e| self.fold_expression(e))
+    }
+}
+
+pub fn noop_fold_program<F: Folder>(folder: &mut F, program: Program) -> Program {
+    Program {
+        body: program
+            .body
+            .into_iter()
+            .map(|statement| folder.fold_spanned_statement(statement))
+            .collect(),
+        block_params: program.block_params,
+    }
+}
+
+pub fn noop_fold_statement<F: Folder>(folder: &mut F, statement: Statement) -> Statement {
+    match statement {
+        Statement::MustacheStatement(mustache) => {
+            Statement::MustacheStatement(folder.fold_mustache(mustache))
+        }
+        Statement::BlockStatement(block) => Statement::BlockStatement(folder.fold_block(block)),
+        Statement::PartialStatement(partial) => {
+            Statement::PartialStatement(folder.fold_partial(partial))
+        }
+        Statement::MustacheComment(comment) => {
+            Statement::MustacheComment(folder.fold_mustache_comment(comment))
+        }
+        Statement::TextNode(text) => Statement::TextNode(folder.fold_text(text)),
+        Statement::ElementNode(element) => Statement::ElementNode(folder.fold_element(element)),
+    }
+}
+
+pub fn noop_fold_element<F: Folder>(folder: &mut F, element: ElementNode) -> ElementNode {
+    ElementNode {
+        tag: element.tag,
+        self_closing: element.self_closing,
+        attributes: element
+            .attributes
+            .into_iter()
+            .map(|attr| folder.fold_attr(attr))
+            .collect(),
+        block_params: element.block_params,
+        modifiers: element
+            .modifiers
+            .into_iter()
+            .map(|modifier| folder.fold_modifier(modifier))
+            .collect(),
+        comments: element
+            .comments
+            .into_iter()
+            .map(|comment| folder.fold_mustache_comment(comment))
+            .collect(),
+        children: element
+            .children
+            .into_iter()
+            .map(|statement| folder.fold_spanned_statement(statement))
+            .collect(),
+    }
+}
+
+pub fn noop_fold_attr<F: Folder>(folder: &mut F, attr: AttrNode) -> AttrNode {
+    let value = match attr.value {
+        AttrValue::TextNode(text) => AttrValue::TextNode(folder.fold_text(text)),
+        AttrValue::MustacheStatement(mustache) => {
+            AttrValue::MustacheStatement(folder.fold_mustache(mustache))
+        }
+        AttrValue::ConcatStatement(concat) => AttrValue::ConcatStatement(ConcatStatement {
+            parts: concat
+                .parts
+                .into_iter()
+                .map(|part| match part {
+                    ConcatParts::TextNode(text) => ConcatParts::TextNode(folder.fold_text(text)),
+                    ConcatParts::MustacheStatement(mustache) => {
+                        ConcatParts::MustacheStatement(folder.fold_mustache(mustache))
+                    }
+                })
+                .collect(),
+        }),
+    };
+
+    AttrNode {
+        name: attr.name,
+        value,
+    }
+}
+
+pub fn noop_fold_block<F: Folder>(
+    folder: &mut F,
+    block: BlockStatement,
+) -> BlockStatement {
+    BlockStatement {
+        path: block.path,
+        params: block
+            .params
+            .into_iter()
+            .map(|param| folder.fold_spanned_expression(param))
+            .collect(),
+        hash: noop_fold_hash(folder, block.hash),
+        program: folder.fold_program(block.program),
+        inverse: block.inverse.map(|inverse| folder.fold_program(inverse)),
+    }
+}
+
+pub fn noop_fold_mustache<F: Folder>(
+    folder: &mut F,
+    mustache: MustacheStatement,
+) -> MustacheStatement {
+    MustacheStatement {
+        path: mustache.path,
+        params: mustache
+            .params
+            .into_iter()
+            .map(|param| folder.fold_spanned_expression(param))
+            .collect(),
+        hash: noop_fold_hash(folder, mustache.hash),
+        escaped: mustache.escaped,
+    }
+}
+
+pub fn noop_fold_modifier<F: Folder>(
+    folder: &mut F,
+    modifier: ElementModifierStatement,
+) -> ElementModifierStatement {
+    ElementModifierStatement {
+        path: modifier.path,
+        params: modifier
+            .params
+            .into_iter()
+            .map(|param| folder.fold_spanned_expression(param))
+            .collect(),
+        hash: noop_fold_hash(folder, modifier.hash),
+    }
+}
+
+pub fn noop_fold_partial<F: Folder>(
+    folder: &mut F,
+    mut partial: PartialStatement,
+) -> PartialStatement {
+    partial.params = partial
+        .params
+        .into_iter()
+        .map(|param| folder.fold_spanned_expression(param))
+        .collect();
+    partial.hash = noop_fold_hash(folder, partial.hash);
+    partial
+}
+
+pub fn noop_fold_expression<F: Folder>(
+    folder: &mut F,
+    expression: Expression,
+) -> Expression {
+    match expression {
+        Expression::SubExpression(mut sub) => {
+            sub.params = sub
+                .params
+                .into_iter()
+                .map(|param| folder.fold_spanned_expression(param))
+                .collect();
+            sub.hash = noop_fold_hash(folder, sub.hash);
+            Expression::SubExpression(sub)
+        }
+        other => other,
+    }
+}
+
+pub fn noop_fold_hash<F: Folder>(folder: &mut F, hash: Hash) -> Hash {
+    Hash {
+        pairs: hash
+            .pairs
+            .into_iter()
+            .map(|pair| HashPair {
+                key: pair.key,
+                value: folder.fold_spanned_expression(pair.value),
+            })
+            .collect(),
+    }
+}